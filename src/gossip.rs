@@ -0,0 +1,250 @@
+use crate::metrics::Metrics;
+use crate::SharedState;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::net::UdpSocket;
+
+// UDP gossip: peers periodically exchange their own and forwarded peer metrics.
+
+pub type NodeId = u64;
+
+const MAX_DATAGRAM_SIZE: usize = 4096;
+
+const FORWARD_FANOUT: usize = 3;
+
+/// Entries older than `EVICTION_TICKS * refresh_interval` are considered stale and dropped.
+const EVICTION_TICKS: u32 = 5;
+
+pub type PeerMetrics = HashMap<NodeId, (Instant, u64, Metrics)>;
+
+#[derive(Serialize, Deserialize)]
+struct GossipPacket {
+    node_id: NodeId,
+    seq: u64,
+    metrics: Metrics,
+}
+
+pub fn new_node_id() -> NodeId {
+    rand::thread_rng().gen()
+}
+
+pub async fn bind(port: u16) -> std::io::Result<UdpSocket> {
+    UdpSocket::bind(("0.0.0.0", port)).await
+}
+
+pub async fn gossip_tick(
+    socket: &UdpSocket,
+    state: &SharedState,
+    peers: &[SocketAddr],
+    node_id: NodeId,
+    seq: u64,
+    refresh_interval: u16,
+) {
+    let metrics = match state.write() {
+        Ok(mut state_guard) => {
+            evict_stale(&mut state_guard.peer_metrics, refresh_interval);
+            Some(state_guard.metrics.clone())
+        }
+        Err(_) => {
+            tracing::warn!("Failed to aquire write lock");
+            None
+        }
+    };
+
+    let Some(metrics) = metrics else { return };
+
+    let packet = GossipPacket {
+        node_id,
+        seq,
+        metrics,
+    };
+    broadcast(socket, peers, &packet).await;
+
+    forward_known_entries(socket, state, peers, node_id).await;
+}
+
+async fn send_packet(socket: &UdpSocket, peer: &SocketAddr, packet: &GossipPacket) {
+    let Ok(bytes) = serde_json::to_vec(packet) else {
+        tracing::warn!("Failed to serialize gossip packet");
+        return;
+    };
+
+    if bytes.len() > MAX_DATAGRAM_SIZE {
+        tracing::warn!(
+            size = bytes.len(),
+            max = MAX_DATAGRAM_SIZE,
+            "Gossip packet too large, dropping"
+        );
+        return;
+    }
+
+    if let Err(err) = socket.send_to(&bytes, peer).await {
+        tracing::warn!(%peer, %err, "Failed to send gossip packet");
+    }
+}
+
+async fn broadcast(socket: &UdpSocket, peers: &[SocketAddr], packet: &GossipPacket) {
+    for peer in peers {
+        send_packet(socket, peer, packet).await;
+    }
+}
+
+async fn forward_known_entries(
+    socket: &UdpSocket,
+    state: &SharedState,
+    peers: &[SocketAddr],
+    node_id: NodeId,
+) {
+    if peers.is_empty() {
+        return;
+    }
+
+    let mut entries: Vec<(NodeId, Instant, u64, Metrics)> = match state.read() {
+        Ok(state_guard) => state_guard
+            .peer_metrics
+            .iter()
+            .filter(|(id, _)| **id != node_id)
+            .map(|(id, (seen, seq, metrics))| (*id, *seen, *seq, metrics.clone()))
+            .collect(),
+        Err(_) => return,
+    };
+
+    entries.sort_by_key(|(_, seen, _, _)| std::cmp::Reverse(*seen));
+    entries.truncate(FORWARD_FANOUT);
+
+    if entries.is_empty() {
+        return;
+    }
+
+    let Some(peer) = peers.choose(&mut rand::thread_rng()) else {
+        return;
+    };
+
+    for (id, _, seq, metrics) in entries {
+        let packet = GossipPacket {
+            node_id: id,
+            seq,
+            metrics,
+        };
+        send_packet(socket, peer, &packet).await;
+    }
+}
+
+pub async fn listen(socket: Arc<UdpSocket>, state: SharedState, node_id: NodeId) {
+    let mut buf = [0u8; MAX_DATAGRAM_SIZE];
+
+    loop {
+        let (len, _) = match socket.recv_from(&mut buf).await {
+            Ok(received) => received,
+            Err(err) => {
+                tracing::warn!(%err, "Failed to receive gossip packet");
+                continue;
+            }
+        };
+
+        let Ok(packet) = serde_json::from_slice::<GossipPacket>(&buf[..len]) else {
+            continue;
+        };
+
+        if packet.node_id == node_id {
+            continue;
+        }
+
+        if let Ok(mut state_guard) = state.write() {
+            merge(&mut state_guard.peer_metrics, packet);
+        } else {
+            tracing::warn!("Failed to aquire write lock");
+        }
+    }
+}
+
+fn merge(peer_metrics: &mut PeerMetrics, packet: GossipPacket) {
+    let is_newer = match peer_metrics.get(&packet.node_id) {
+        Some((_, known_seq, _)) => packet.seq > *known_seq,
+        None => true,
+    };
+
+    if !is_newer {
+        return;
+    }
+
+    peer_metrics.insert(packet.node_id, (Instant::now(), packet.seq, packet.metrics));
+}
+
+fn evict_stale(peer_metrics: &mut PeerMetrics, refresh_interval: u16) {
+    let max_age = Duration::from_secs(u64::from(refresh_interval) * u64::from(EVICTION_TICKS));
+    peer_metrics.retain(|_, (seen, _, _)| seen.elapsed() < max_age);
+}
+
+#[cfg(test)]
+mod gossip_tests {
+    use super::*;
+
+    fn packet(node_id: NodeId, seq: u64) -> GossipPacket {
+        GossipPacket {
+            node_id,
+            seq,
+            metrics: Metrics::default(),
+        }
+    }
+
+    #[test]
+    fn merge_accepts_first_insert() {
+        let mut peer_metrics = PeerMetrics::new();
+        merge(&mut peer_metrics, packet(1, 1));
+        assert_eq!(peer_metrics.get(&1).unwrap().1, 1);
+    }
+
+    #[test]
+    fn merge_accepts_newer_seq() {
+        let mut peer_metrics = PeerMetrics::new();
+        merge(&mut peer_metrics, packet(1, 1));
+        merge(&mut peer_metrics, packet(1, 2));
+        assert_eq!(peer_metrics.get(&1).unwrap().1, 2);
+    }
+
+    #[test]
+    fn merge_rejects_older_seq() {
+        let mut peer_metrics = PeerMetrics::new();
+        merge(&mut peer_metrics, packet(1, 5));
+        merge(&mut peer_metrics, packet(1, 2));
+        assert_eq!(peer_metrics.get(&1).unwrap().1, 5);
+    }
+
+    #[test]
+    fn merge_rejects_equal_seq() {
+        let mut peer_metrics = PeerMetrics::new();
+        merge(&mut peer_metrics, packet(1, 5));
+        merge(&mut peer_metrics, packet(1, 5));
+        assert_eq!(peer_metrics.get(&1).unwrap().1, 5);
+    }
+
+    #[test]
+    fn evict_stale_drops_entries_past_max_age() {
+        let refresh_interval: u16 = 1;
+        let max_age = Duration::from_secs(u64::from(refresh_interval) * u64::from(EVICTION_TICKS));
+
+        let mut peer_metrics = PeerMetrics::new();
+        peer_metrics.insert(1, (Instant::now(), 1, Metrics::default()));
+        peer_metrics.insert(
+            2,
+            (
+                Instant::now() - max_age - Duration::from_secs(1),
+                1,
+                Metrics::default(),
+            ),
+        );
+
+        evict_stale(&mut peer_metrics, refresh_interval);
+
+        assert!(peer_metrics.contains_key(&1));
+        assert!(!peer_metrics.contains_key(&2));
+    }
+}
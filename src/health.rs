@@ -0,0 +1,85 @@
+use crate::ServiceDescription;
+use crate::SharedState;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthCheckKind {
+    #[default]
+    Http,
+    Tcp,
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct HealthStatus {
+    pub up: bool,
+    pub latency_ms: u128,
+    pub consecutive_failures: u32,
+}
+
+type HttpClient = hyper::Client<hyper_tls::HttpsConnector<hyper::client::HttpConnector>>;
+
+async fn check_http(client: &HttpClient, route: &str) -> (bool, Duration) {
+    let start = Instant::now();
+    let uri = match route.parse() {
+        Ok(uri) => uri,
+        Err(_) => return (false, start.elapsed()),
+    };
+
+    let up = match client.get(uri).await {
+        Ok(response) => response.status().is_success() || response.status().is_redirection(),
+        Err(_) => false,
+    };
+    (up, start.elapsed())
+}
+
+async fn check_tcp(route: &str) -> (bool, Duration) {
+    let start = Instant::now();
+    let up = tokio::net::TcpStream::connect(route).await.is_ok();
+    (up, start.elapsed())
+}
+
+/// Polls every configured service's `route` on `interval` seconds, recording
+/// latency, up/down status and a rolling count of consecutive failures in
+/// `State.service_health`.
+pub async fn run_health_checks(state: SharedState, interval: u16) {
+    let mut tick = tokio::time::interval(Duration::from_secs(interval.into()));
+    let client = hyper::Client::builder().build::<_, hyper::Body>(hyper_tls::HttpsConnector::new());
+
+    loop {
+        tick.tick().await;
+
+        let services: Vec<(String, ServiceDescription)> = match state.read() {
+            Ok(state_guard) => state_guard
+                .services
+                .iter()
+                .map(|(name, desc)| (name.clone(), desc.clone()))
+                .collect(),
+            Err(_) => {
+                tracing::warn!("Failed to aquire state lock");
+                continue;
+            }
+        };
+
+        for (name, desc) in services {
+            let (up, elapsed) = match desc.health_check {
+                HealthCheckKind::Http => check_http(&client, &desc.route).await,
+                HealthCheckKind::Tcp => check_tcp(&desc.route).await,
+            };
+
+            if let Ok(mut state_guard) = state.write() {
+                let status = state_guard.service_health.entry(name).or_default();
+                status.latency_ms = elapsed.as_millis();
+                status.up = up;
+                if up {
+                    status.consecutive_failures = 0;
+                } else {
+                    status.consecutive_failures += 1;
+                }
+            } else {
+                tracing::warn!("Failed to aquire write lock");
+            }
+        }
+    }
+}
@@ -1,32 +1,40 @@
+mod gossip;
+mod health;
 mod metrics;
 mod parser;
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     fs::read_to_string,
     net::SocketAddr,
     path::Path,
     str,
     sync::{Arc, RwLock},
-    time::Duration,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::Result;
 use askama::Template;
 use axum::{
     body::{boxed, Full},
-    http::{header, StatusCode, Uri},
+    extract::Query,
+    http::{header, HeaderMap, HeaderName, HeaderValue, Request, StatusCode, Uri},
+    middleware::{self, Next},
     response::{Html, IntoResponse, Json, Response},
     routing::get,
     Extension, Router,
 };
 use rust_embed::RustEmbed;
 use serde::{Deserialize, Serialize};
+use tracing::Instrument;
+use ulid::Ulid;
 
 #[derive(Serialize, Deserialize, Clone)]
-struct ServiceDescription {
+pub(crate) struct ServiceDescription {
     description: String,
-    route: String,
+    pub(crate) route: String,
+    #[serde(default)]
+    pub(crate) health_check: health::HealthCheckKind,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -37,6 +45,10 @@ struct Configuration {
     nixos_current_system: bool,
     services: HashMap<String, ServiceDescription>,
     refresh_interval: u16,
+    gossip_port: Option<u16>,
+    peers: Vec<SocketAddr>,
+    health_check_interval: u16,
+    history_len: usize,
 }
 
 impl Default for Configuration {
@@ -46,16 +58,31 @@ impl Default for Configuration {
             nixos_current_system: false,
             services: HashMap::new(),
             refresh_interval: 10,
+            gossip_port: None,
+            peers: Vec::new(),
+            health_check_interval: 30,
+            history_len: 120,
         }
     }
 }
 
+#[derive(Serialize, Clone)]
+struct HistoryEntry {
+    timestamp: u64,
+    metrics: metrics::Metrics,
+}
+
 struct State {
     nixos_current_system: bool,
     services: HashMap<String, ServiceDescription>,
     refresh_interval: u16,
     last_metrics: metrics::Metrics,
     metrics: metrics::Metrics,
+    node_id: gossip::NodeId,
+    peer_metrics: gossip::PeerMetrics,
+    service_health: HashMap<String, health::HealthStatus>,
+    history: VecDeque<HistoryEntry>,
+    history_len: usize,
 }
 
 type SharedState = Arc<RwLock<State>>;
@@ -71,6 +98,8 @@ fn load_configuration(path: &Path) -> Configuration {
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
     let args: Vec<String> = std::env::args().collect();
     let config_path = &args.get(1).expect("Expected argument to config path");
     let config_path = Path::new(&config_path);
@@ -82,12 +111,19 @@ async fn main() -> Result<()> {
 
     let metrics = metrics::get_metrics(&init_metrics, config.nixos_current_system)?;
 
+    let node_id = gossip::new_node_id();
+
     let state = State {
         nixos_current_system: config.nixos_current_system,
         services: config.services,
         refresh_interval: config.refresh_interval,
         last_metrics: init_metrics,
         metrics,
+        node_id,
+        peer_metrics: HashMap::new(),
+        service_health: HashMap::new(),
+        history: VecDeque::new(),
+        history_len: config.history_len,
     };
 
     let state = Arc::new(RwLock::new(state));
@@ -95,33 +131,112 @@ async fn main() -> Result<()> {
 
     let refresh_stat = tokio::task::spawn(refresh_metrics(stat_state, config.refresh_interval));
 
+    let health_state = state.clone();
+    tokio::task::spawn(health::run_health_checks(
+        health_state,
+        config.health_check_interval,
+    ));
+
+    if !config.peers.is_empty() {
+        if let Some(gossip_port) = config.gossip_port {
+            let socket = Arc::new(gossip::bind(gossip_port).await?);
+
+            let listen_socket = socket.clone();
+            let gossip_state = state.clone();
+            tokio::task::spawn(gossip::listen(listen_socket, gossip_state, node_id));
+
+            let gossip_state = state.clone();
+            tokio::task::spawn(run_gossip(
+                socket,
+                gossip_state,
+                config.peers,
+                node_id,
+                config.refresh_interval,
+            ));
+        } else {
+            tracing::warn!("peers configured without a gossipPort, gossip disabled");
+        }
+    }
+
     let app = Router::new()
         .route("/", get(root))
         .route("/metrics", get(metrics_api))
+        .route("/metrics/history", get(metrics_history_api))
         .route("/assets/*file", get(assets))
-        .route_layer(Extension(state));
+        .route_layer(Extension(state))
+        .layer(middleware::from_fn(request_id_middleware));
     let server = axum::Server::bind(&addr).serve(app.into_make_service());
-    println!("Starting AnsÃ­ne on {addr}...");
+    tracing::info!(%addr, "Starting AnsÃ­ne");
     let (_, _) = tokio::join!(refresh_stat, server);
     Ok(())
 }
 
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Tags every request with a lexicographically-sortable id, wraps the
+/// handler in a span carrying that id, and echoes it back so a bad
+/// response can be correlated with the logs that explain it.
+async fn request_id_middleware<B>(req: Request<B>, next: Next<B>) -> Response {
+    let request_id = Ulid::new().to_string();
+    let span = tracing::info_span!("request", request_id = %request_id, method = %req.method(), uri = %req.uri());
+
+    async move {
+        let mut response = next.run(req).await;
+        if let Ok(value) = HeaderValue::from_str(&request_id) {
+            response
+                .headers_mut()
+                .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+        }
+        response
+    }
+    .instrument(span)
+    .await
+}
+
+async fn run_gossip(
+    socket: Arc<tokio::net::UdpSocket>,
+    state: SharedState,
+    peers: Vec<SocketAddr>,
+    node_id: gossip::NodeId,
+    refresh_interval: u16,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(refresh_interval.into()));
+    let mut seq: u64 = 0;
+
+    loop {
+        interval.tick().await;
+        seq += 1;
+        gossip::gossip_tick(&socket, &state, &peers, node_id, seq, refresh_interval).await;
+    }
+}
+
 async fn refresh_metrics(state: SharedState, refresh_interval: u16) -> Result<()> {
     let mut interval = tokio::time::interval(Duration::from_secs(refresh_interval.into()));
 
     loop {
         interval.tick().await;
         if let Ok(mut state_guard) = state.write() {
-            if let Ok(metrics) =
-                metrics::get_metrics(&state_guard.last_metrics, state_guard.nixos_current_system)
+            match metrics::get_metrics(&state_guard.last_metrics, state_guard.nixos_current_system)
             {
-                state_guard.last_metrics = state_guard.metrics.clone();
-                state_guard.metrics = metrics;
-            } else {
-                eprintln!("Failed to refresh metrics")
+                Ok(metrics) => {
+                    state_guard.last_metrics = state_guard.metrics.clone();
+                    state_guard.metrics = metrics.clone();
+
+                    let timestamp = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map(|d| d.as_secs())
+                        .unwrap_or(0);
+                    state_guard
+                        .history
+                        .push_back(HistoryEntry { timestamp, metrics });
+                    while state_guard.history.len() > state_guard.history_len {
+                        state_guard.history.pop_front();
+                    }
+                }
+                Err(err) => tracing::warn!(?err, "Failed to refresh metrics"),
             }
         } else {
-            eprintln!("Failed to aquire write lock")
+            tracing::warn!("Failed to aquire write lock")
         }
     }
 }
@@ -169,6 +284,9 @@ async fn assets(uri: Uri) -> impl IntoResponse {
 struct IndexTemplate {
     services: HashMap<String, ServiceDescription>,
     refresh_interval: u16,
+    metrics: metrics::Metrics,
+    peer_metrics: HashMap<gossip::NodeId, metrics::Metrics>,
+    service_health: HashMap<String, health::HealthStatus>,
 }
 
 struct HtmlTemplate<T>(T);
@@ -192,24 +310,111 @@ where
 async fn root(Extension(state): Extension<SharedState>) -> impl IntoResponse {
     match state.read() {
         Ok(state_guard) => {
+            let peer_metrics = state_guard
+                .peer_metrics
+                .iter()
+                .map(|(node_id, (_, _, metrics))| (*node_id, metrics.clone()))
+                .collect();
             let template = IndexTemplate {
                 services: state_guard.services.clone(),
                 refresh_interval: state_guard.refresh_interval,
+                metrics: state_guard.metrics.clone(),
+                peer_metrics,
+                service_health: state_guard.service_health.clone(),
             };
             HtmlTemplate(template).into_response()
         }
         Err(_) => {
-            eprintln!("Failed to aquire state lock");
+            tracing::error!("Failed to aquire state lock");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error").into_response()
+        }
+    }
+}
+
+const CBOR_MIME: &str = "application/cbor";
+
+/// Encodes `value` as CBOR when the client's `Accept` header asks for
+/// `application/cbor`, falling back to the default JSON encoding otherwise.
+fn negotiate<T: Serialize>(headers: &HeaderMap, value: T) -> Response {
+    let wants_cbor = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.contains(CBOR_MIME))
+        .unwrap_or(false);
+
+    if !wants_cbor {
+        return Json(value).into_response();
+    }
+
+    let mut body = Vec::new();
+    match ciborium::ser::into_writer(&value, &mut body) {
+        Ok(()) => Response::builder()
+            .header(header::CONTENT_TYPE, CBOR_MIME)
+            .body(boxed(Full::from(body)))
+            .unwrap(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to encode CBOR response. Error: {err}"),
+        )
+            .into_response(),
+    }
+}
+
+#[derive(Serialize)]
+struct MetricsResponse {
+    cluster: HashMap<gossip::NodeId, metrics::Metrics>,
+    services: HashMap<String, health::HealthStatus>,
+}
+
+async fn metrics_api(
+    headers: HeaderMap,
+    Extension(state): Extension<SharedState>,
+) -> impl IntoResponse {
+    match state.read() {
+        Ok(state_guard) => {
+            let mut cluster: HashMap<gossip::NodeId, metrics::Metrics> = state_guard
+                .peer_metrics
+                .iter()
+                .map(|(node_id, (_, _, metrics))| (*node_id, metrics.clone()))
+                .collect();
+            cluster.insert(state_guard.node_id, state_guard.metrics.clone());
+            let response = MetricsResponse {
+                cluster,
+                services: state_guard.service_health.clone(),
+            };
+            negotiate(&headers, response)
+        }
+        Err(_) => {
+            tracing::error!("Failed to acquire state lock");
             (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error").into_response()
         }
     }
 }
 
-async fn metrics_api(Extension(state): Extension<SharedState>) -> impl IntoResponse {
+#[derive(Deserialize)]
+struct HistoryQuery {
+    since: Option<u64>,
+}
+
+async fn metrics_history_api(
+    headers: HeaderMap,
+    Query(query): Query<HistoryQuery>,
+    Extension(state): Extension<SharedState>,
+) -> impl IntoResponse {
     match state.read() {
-        Ok(state_guard) => Json(state_guard.metrics.clone()).into_response(),
+        Ok(state_guard) => {
+            let history: Vec<&HistoryEntry> = match query.since {
+                Some(since) => state_guard
+                    .history
+                    .iter()
+                    .filter(|entry| entry.timestamp >= since)
+                    .collect(),
+                None => state_guard.history.iter().collect(),
+            };
+            negotiate(&headers, history)
+        }
         Err(_) => {
-            eprintln!("Failed to acquire state lock");
+            tracing::error!("Failed to acquire state lock");
             (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error").into_response()
         }
     }
@@ -2,7 +2,7 @@ use crate::parser::{
     parse_meminfo, parse_nix_store_path, parse_stat, parse_swaps, parse_uptime, MemInfo, Stat,
     Swaps,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::{fs, ops::Sub, str, time::Duration};
 
 #[derive(Debug)]
@@ -16,7 +16,10 @@ fn read_file(fp: &str) -> Result<String, MetricError> {
     let s = fs::read_to_string(fp);
     match s {
         Ok(s) => Ok(s),
-        Err(_) => Err(MetricError::FileRead(format!("Unable to read {}", fp))),
+        Err(_) => {
+            tracing::warn!(file = fp, "Unable to read file");
+            Err(MetricError::FileRead(format!("Unable to read {}", fp)))
+        }
     }
 }
 
@@ -26,7 +29,10 @@ fn read_link(fp: &str) -> Result<String, MetricError> {
         .and_then(|l| l.to_str().map(String::from));
     match link {
         Some(l) => Ok(l),
-        None => Err(MetricError::LinkRead(format!("Unable to read link {}", fp))),
+        None => {
+            tracing::warn!(file = fp, "Unable to read link");
+            Err(MetricError::LinkRead(format!("Unable to read link {}", fp)))
+        }
     }
 }
 
@@ -35,13 +41,19 @@ fn read_nixos_current_system() -> Result<String, MetricError> {
     let parsed_link = parse_nix_store_path(&link);
     match parsed_link {
         Ok((_, current_system)) => Ok(current_system.to_string()),
-        Err(_) => Err(MetricError::MetricParse(
-            "Unable to parse current system".to_string(),
-        )),
+        Err(_) => {
+            tracing::error!(
+                file = "/run/current-system",
+                "Unable to parse current system"
+            );
+            Err(MetricError::MetricParse(
+                "Unable to parse current system".to_string(),
+            ))
+        }
     }
 }
 
-#[derive(Serialize, Clone, Default)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct Cpu {
     pub total: usize,
     pub used: usize,
@@ -77,7 +89,7 @@ impl<'a, 'b> Sub<&'b Cpu> for &'a Cpu {
     }
 }
 
-#[derive(Serialize, Clone, Default)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct Memory {
     pub total: usize,
     pub used: usize,
@@ -96,7 +108,7 @@ impl From<MemInfo> for Memory {
     }
 }
 
-#[derive(Serialize, Clone, Default)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct Swap {
     pub size: usize,
     pub used: usize,
@@ -114,11 +126,13 @@ impl From<Swaps> for Swap {
     }
 }
 
-#[derive(Serialize, Clone, Default)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct Metrics {
     pub uptime: Duration,
     pub cpu_since_boot: Cpu,
     pub cpu_delta: Cpu,
+    pub per_core_since_boot: Vec<Cpu>,
+    pub per_core: Vec<Cpu>,
     pub memory: Memory,
     pub swap: Swap,
     pub current_system: Option<String>,
@@ -128,10 +142,13 @@ fn get_metric<T>(fp: &str, f: fn(&str) -> nom::IResult<&str, T>) -> Result<T, Me
     let metric = read_file(fp)?;
     match f(&metric) {
         Ok((_, parsed_metric)) => Ok(parsed_metric),
-        Err(_) => Err(MetricError::MetricParse(format!(
-            "Unable to parse metric from {}",
-            fp
-        ))),
+        Err(_) => {
+            tracing::error!(file = fp, "Unable to parse metric");
+            Err(MetricError::MetricParse(format!(
+                "Unable to parse metric from {}",
+                fp
+            )))
+        }
     }
 }
 
@@ -142,8 +159,24 @@ pub fn get_metrics(
     let memory = Memory::from(get_metric("/proc/meminfo", parse_meminfo)?);
     let uptime = get_metric("/proc/uptime", parse_uptime)?;
     let swap = Swap::from(get_metric("/proc/swaps", parse_swaps)?);
-    let cpu_since_boot = Cpu::from(get_metric("/proc/stat", parse_stat)?);
+    let (stat, core_stats) = get_metric("/proc/stat", parse_stat)?;
+    let cpu_since_boot = Cpu::from(stat);
     let cpu_delta = &cpu_since_boot - &last_metrics.cpu_since_boot;
+
+    let per_core_since_boot: Vec<Cpu> = core_stats.into_iter().map(Cpu::from).collect();
+    let default_core = Cpu::default();
+    let per_core: Vec<Cpu> = per_core_since_boot
+        .iter()
+        .enumerate()
+        .map(|(i, current)| {
+            let previous = last_metrics
+                .per_core_since_boot
+                .get(i)
+                .unwrap_or(&default_core);
+            current - previous
+        })
+        .collect();
+
     let current_system = if get_current_system {
         Some(read_nixos_current_system()?)
     } else {
@@ -154,6 +187,8 @@ pub fn get_metrics(
         uptime,
         cpu_since_boot,
         cpu_delta,
+        per_core_since_boot,
+        per_core,
         memory,
         swap,
         current_system,
@@ -81,23 +81,20 @@ fn parse_f64(i: &str) -> IResult<&str, f64> {
     ws(double)(i)
 }
 
-pub fn parse_stat(i: &str) -> IResult<&str, Stat> {
-    let (i, _) = take_until("cpu ")(i)?;
-    let parser = tuple((
-        parse_usize,
-        parse_usize,
-        parse_usize,
-        parse_usize,
-        parse_usize,
-        parse_usize,
-        parse_usize,
-        parse_usize,
-        parse_usize,
-        parse_usize,
-    ));
-
+fn parse_stat_fields(i: &str) -> IResult<&str, Stat> {
     let (i, (user, nice, system, idle, iowait, irq, softirq, steal, guest, guest_nice)) =
-        preceded(tag("cpu "), parser)(i)?;
+        tuple((
+            parse_usize,
+            parse_usize,
+            parse_usize,
+            parse_usize,
+            parse_usize,
+            parse_usize,
+            parse_usize,
+            parse_usize,
+            parse_usize,
+            parse_usize,
+        ))(i)?;
     Ok((
         i,
         Stat {
@@ -115,6 +112,19 @@ pub fn parse_stat(i: &str) -> IResult<&str, Stat> {
     ))
 }
 
+fn parse_core_stat_line(i: &str) -> IResult<&str, Stat> {
+    preceded(tuple((tag("cpu"), digit1, char(' '))), parse_stat_fields)(i)
+}
+
+/// Parses the aggregate `cpu ` line from `/proc/stat`, along with every
+/// per-core `cpu0`, `cpu1`, ... line that follows it.
+pub fn parse_stat(i: &str) -> IResult<&str, (Stat, Vec<Stat>)> {
+    let (i, _) = take_until("cpu ")(i)?;
+    let (i, aggregate) = preceded(tag("cpu "), parse_stat_fields)(i)?;
+    let (i, per_core) = many0(parse_core_stat_line)(i)?;
+    Ok((i, (aggregate, per_core)))
+}
+
 pub fn parse_uptime(i: &str) -> IResult<&str, Duration> {
     let (i, u) = parse_f64(i)?;
     Ok((i, Duration::from_secs_f64(u)))
@@ -189,7 +199,7 @@ cpu5 1215377 766 152806 14948197 15296 0 13306 0 0 0
 cpu6 1218276 832 158639 14917222 14966 0 4001 0 0 0
 cpu7 1118264 821 174536 14959651 14820 0 26297 0 0 0
 ";
-        let (_, stat) = parse_stat(proc_stat).unwrap();
+        let (_, (stat, per_core)) = parse_stat(proc_stat).unwrap();
         assert_eq!(
             stat,
             Stat {
@@ -205,6 +215,37 @@ cpu7 1118264 821 174536 14959651 14820 0 26297 0 0 0
                 guest_nice: 0
             }
         );
+        assert_eq!(per_core.len(), 8);
+        assert_eq!(
+            per_core[0],
+            Stat {
+                user: 1209513,
+                nice: 784,
+                system: 169115,
+                idle: 14910230,
+                iowait: 15511,
+                irq: 0,
+                softirq: 34945,
+                steal: 0,
+                guest: 0,
+                guest_nice: 0
+            }
+        );
+        assert_eq!(
+            per_core[7],
+            Stat {
+                user: 1118264,
+                nice: 821,
+                system: 174536,
+                idle: 14959651,
+                iowait: 14820,
+                irq: 0,
+                softirq: 26297,
+                steal: 0,
+                guest: 0,
+                guest_nice: 0
+            }
+        );
     }
 
     #[test]